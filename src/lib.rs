@@ -2,10 +2,62 @@
 // a little bit from myself, Graydon Hoare <graydon@pobox.com>. It is licensed
 // under MIT + ASL2.0 terms.
 
+use std::ops::{Range, RangeInclusive, RangeTo, RangeToInclusive};
+
+/// A range type `gen_in` can accept: `a..b`, `a..=b`, `..b`, or `..=b` over
+/// `usize`.
+pub trait GenRange {
+    /// Returns `None` when the range contains no values, otherwise
+    /// `Some((start, bound))` such that the generated value is
+    /// `start + gen(bound)`.
+    fn gen_bounds(&self) -> Option<(usize, usize)>;
+}
+
+impl GenRange for Range<usize> {
+    fn gen_bounds(&self) -> Option<(usize, usize)> {
+        if self.start >= self.end {
+            None
+        } else {
+            Some((self.start, self.end - self.start - 1))
+        }
+    }
+}
+
+impl GenRange for RangeInclusive<usize> {
+    fn gen_bounds(&self) -> Option<(usize, usize)> {
+        if self.start() > self.end() {
+            None
+        } else {
+            Some((*self.start(), self.end() - self.start()))
+        }
+    }
+}
+
+impl GenRange for RangeTo<usize> {
+    fn gen_bounds(&self) -> Option<(usize, usize)> {
+        (0..self.end).gen_bounds()
+    }
+}
+
+impl GenRange for RangeToInclusive<usize> {
+    fn gen_bounds(&self) -> Option<(usize, usize)> {
+        Some((0, self.end))
+    }
+}
+
+/// A value drawn from one of two sub-generators, as produced by
+/// `Gen::gen_either`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
 pub struct Gen {
     started: bool,
     v: Vec<(usize, usize)>,
     p: usize,
+    iters: usize,
 }
 
 impl Gen {
@@ -14,6 +66,7 @@ impl Gen {
             started: false,
             v: Vec::new(),
             p: 0,
+            iters: 0,
         }
     }
 
@@ -24,6 +77,7 @@ impl Gen {
     pub fn done(&mut self) -> bool {
         if !self.started {
             self.started = true;
+            self.iters = 1;
             return false;
         }
 
@@ -32,12 +86,48 @@ impl Gen {
                 self.v[i].0 += 1;
                 self.v.truncate(i + 1);
                 self.p = 0;
+                self.iters += 1;
                 return false;
             }
         }
         true
     }
 
+    /// Runs `f` to exhaustion in a fresh, throwaway `Gen` and returns how
+    /// many iterations of the `done()` loop it performs, i.e. the total size
+    /// of the exhaustive state-space `f` explores. The values `f` produces
+    /// are discarded; only the iteration count is kept. Useful for sizing a
+    /// progress bar ahead of (or independent of) an actual exhaustive run.
+    pub fn count(mut f: impl FnMut(&mut Gen)) -> usize {
+        let mut gen = Gen::new();
+        let mut n = 0;
+        while !gen.done() {
+            f(&mut gen);
+            n += 1;
+        }
+        n
+    }
+
+    /// Returns the number of iterations of the exhaustive `done()` loop
+    /// performed so far, including the current one.
+    pub fn position(&self) -> usize {
+        self.iters
+    }
+
+    /// Returns how many iterations of the exhaustive `done()` loop remain,
+    /// given `total` as returned by `Gen::count` run over the same body.
+    pub fn remaining(&self, total: usize) -> usize {
+        total.saturating_sub(self.iters)
+    }
+
+    /// Returns `(position(), total)`, mirroring the `(lower, upper)` shape of
+    /// `Iterator::size_hint`, given `total` as returned by `Gen::count` run
+    /// over the same body. Lets callers drive a progress bar over a long
+    /// exhaustive sweep.
+    pub fn size_hint(&self, total: usize) -> (usize, usize) {
+        (self.iters, total)
+    }
+
     /// Returns a value (eventually every value) between 0 and `bound`
     /// inclusive. Every other value-generating method in this type ultimately
     /// funnels into this method, which is responsible (in concert with `done`)
@@ -58,11 +148,45 @@ impl Gen {
         self.gen(1) == 1
     }
 
+    /// Generates `None`, then `Some(f(self))` (eventually both), using
+    /// `flip` to choose the branch so the enclosing loop visits both.
+    pub fn gen_option<T>(&mut self, mut f: impl FnMut(&mut Self) -> T) -> Option<T> {
+        if self.flip() {
+            Some(f(self))
+        } else {
+            None
+        }
+    }
+
+    /// Generates a value from one of two sub-generators (eventually both),
+    /// using `flip` to choose which side runs.
+    pub fn gen_either<A, B>(
+        &mut self,
+        mut fa: impl FnMut(&mut Self) -> A,
+        mut fb: impl FnMut(&mut Self) -> B,
+    ) -> Either<A, B> {
+        if self.flip() {
+            Either::Right(fb(self))
+        } else {
+            Either::Left(fa(self))
+        }
+    }
+
     /// Selects an element (eventually every element) from `input`.
     pub fn pick<'a, T>(&mut self, input: &'a [T]) -> &'a T {
         &input[self.gen(input.len() - 1)]
     }
 
+    /// Generates a value (eventually every value) within `range`, accepting
+    /// `a..b`, `a..=b`, `..b`, and `..=b`. Unlike `gen`/`pick`, an empty range
+    /// (e.g. `0..0`, as produced by the length of an empty slice) simply
+    /// yields `None` instead of panicking.
+    pub fn gen_in(&mut self, range: impl GenRange) -> Option<usize> {
+        range
+            .gen_bounds()
+            .map(|(start, bound)| start + self.gen(bound))
+    }
+
     /// Generates a variable-length iterator (eventually every such iterator)
     /// that returns the result of repeated calls to `f(gen)`. The iterator has
     /// length <= `bound`.
@@ -88,7 +212,10 @@ impl Gen {
 
     /// Generates a variable-length iterator (eventually every such iterator)
     /// with variable-value elements. The iterator has length <= `len_bound` and
-    /// each element has value <= `elt_bound`.
+    /// each element has value <= `elt_bound`. Like the other iterator-returning
+    /// methods on this type, the result supports the usual `Iterator` adaptors
+    /// (e.g. `.map(...)`) for folding each drawn element into a domain type
+    /// in-line, lazily, without disturbing the range stepping `gen` performs.
     pub fn gen_elts(
         &mut self,
         len_bound: usize,
@@ -155,6 +282,115 @@ impl Gen {
     ) -> impl Iterator<Item = &'data T> + 'gen {
         (0..input.len()).filter_map(move |i| if self.flip() { Some(&input[i]) } else { None })
     }
+
+    /// Generates a `k`-combination (eventually every `k`-combination) of
+    /// distinct elements selected from the `input` array provided, i.e. the
+    /// true mathematical set of `k`-element subsets, each produced exactly
+    /// once. This differs from `gen_fixed_comb`, which builds each element
+    /// via an independent `pick` and so may repeat elements and orderings.
+    /// Yields the single empty combination when `k` is 0, and nothing at all
+    /// when `k` is greater than the length of `input`.
+    pub fn gen_choose<'gen, 'data: 'gen, T>(
+        &'gen mut self,
+        k: usize,
+        input: &'data [T],
+    ) -> impl Iterator<Item = &'data T> + 'gen {
+        let n = input.len();
+        let fixed = if k > n { 0 } else { k };
+        let mut start = 0;
+        let mut left = fixed;
+        self.gen_fixed_by(fixed, move |g| {
+            let idx = start + g.gen(n - left - start);
+            start = idx + 1;
+            left -= 1;
+            &input[idx]
+        })
+    }
+
+    /// Generates a subset (eventually every subset) of the `input` array
+    /// provided, ordered by increasing size: first the empty subset, then
+    /// every 1-element subset, then every 2-element subset, and so on up to
+    /// `input` itself. Equivalent to drawing `k = gen(input.len())` and
+    /// delegating to `gen_choose(k, input)`.
+    pub fn gen_powerset<'gen, 'data: 'gen, T>(
+        &'gen mut self,
+        input: &'data [T],
+    ) -> impl Iterator<Item = &'data T> + 'gen {
+        let k = self.gen(input.len());
+        self.gen_choose(k, input)
+    }
+
+    /// Generates a pair (eventually every pair) of distinct elements from
+    /// `input` with `i<j`. Built directly on `gen_choose`, so the enclosing
+    /// loop visits exactly `C(n, 2)` pairs, each once, with no `(x, x)`
+    /// self-pairs. Returns `None` (rather than panicking) when `input` has
+    /// fewer than 2 elements.
+    pub fn gen_pair<'gen, 'data: 'gen, T>(
+        &'gen mut self,
+        input: &'data [T],
+    ) -> Option<(&'data T, &'data T)> {
+        let mut it = self.gen_choose(2, input);
+        let a = it.next()?;
+        let b = it.next()?;
+        Some((a, b))
+    }
+
+    /// Generates a triple (eventually every triple) of distinct elements
+    /// from `input` with `i<j<k`. See `gen_pair`; likewise returns `None`
+    /// when `input` has fewer than 3 elements.
+    pub fn gen_triple<'gen, 'data: 'gen, T>(
+        &'gen mut self,
+        input: &'data [T],
+    ) -> Option<(&'data T, &'data T, &'data T)> {
+        let mut it = self.gen_choose(3, input);
+        let a = it.next()?;
+        let b = it.next()?;
+        let c = it.next()?;
+        Some((a, b, c))
+    }
+
+    /// Generates every combination (eventually) of the state-spaces explored
+    /// by `fa` and `fb`, i.e. the Cartesian product of whatever `gen`/`pick`
+    /// calls each closure makes. Since `done` already treats successive `gen`
+    /// calls as nested ranges, simply calling `fa` then `fb` in sequence is
+    /// enough for the enclosing `while !done()` loop to walk every pair.
+    pub fn gen_tuple2<A, B>(
+        &mut self,
+        mut fa: impl FnMut(&mut Self) -> A,
+        mut fb: impl FnMut(&mut Self) -> B,
+    ) -> (A, B) {
+        let a = fa(self);
+        let b = fb(self);
+        (a, b)
+    }
+
+    /// Three-way version of `gen_tuple2`.
+    pub fn gen_tuple3<A, B, C>(
+        &mut self,
+        mut fa: impl FnMut(&mut Self) -> A,
+        mut fb: impl FnMut(&mut Self) -> B,
+        mut fc: impl FnMut(&mut Self) -> C,
+    ) -> (A, B, C) {
+        let a = fa(self);
+        let b = fb(self);
+        let c = fc(self);
+        (a, b, c)
+    }
+
+    /// Four-way version of `gen_tuple2`.
+    pub fn gen_tuple4<A, B, C, D>(
+        &mut self,
+        mut fa: impl FnMut(&mut Self) -> A,
+        mut fb: impl FnMut(&mut Self) -> B,
+        mut fc: impl FnMut(&mut Self) -> C,
+        mut fd: impl FnMut(&mut Self) -> D,
+    ) -> (A, B, C, D) {
+        let a = fa(self);
+        let b = fb(self);
+        let c = fc(self);
+        let d = fd(self);
+        (a, b, c, d)
+    }
 }
 
 #[cfg(test)]
@@ -214,4 +450,252 @@ mod tests {
         }
         assert_eq!(i, 1 << 5);
     }
+
+    #[test]
+    fn test_choose() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let choice = gen.gen_choose(3, &vec).collect::<Vec<_>>();
+            assert_eq!(choice.len(), 3);
+            println!("{:?}", choice);
+            i += 1;
+        }
+        // C(5, 3)
+        assert_eq!(i, 10);
+    }
+
+    #[test]
+    fn test_choose_edge_cases() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let choice = gen.gen_choose(0, &vec).collect::<Vec<_>>();
+            assert!(choice.is_empty());
+            i += 1;
+        }
+        assert_eq!(i, 1);
+
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            let choice = gen.gen_choose(6, &vec).collect::<Vec<_>>();
+            assert!(choice.is_empty());
+            i += 1;
+        }
+        assert_eq!(i, 1);
+    }
+
+    #[test]
+    fn test_powerset() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let subset = gen.gen_powerset(&vec).collect::<Vec<_>>();
+            println!("{:?}", subset);
+            i += 1;
+        }
+        // sum of C(5, k) for k in 0..=5
+        assert_eq!(i, 1 << 5);
+    }
+
+    #[test]
+    fn test_tuple2() {
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            let (a, b) = gen.gen_tuple2(|g| g.gen(2), |g| g.flip());
+            println!("{:?}", (a, b));
+            i += 1;
+        }
+        assert_eq!(i, 3 * 2);
+    }
+
+    #[test]
+    fn test_tuple3() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let t = gen.gen_tuple3(|g| g.gen(2), |g| g.flip(), |g| g.pick(&vec));
+            println!("{:?}", t);
+            i += 1;
+        }
+        assert_eq!(i, 3 * 2 * 5);
+    }
+
+    #[test]
+    fn test_tuple4() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let t = gen.gen_tuple4(
+                |g| g.gen(1),
+                |g| g.flip(),
+                |g| g.pick(&vec),
+                |g| g.gen(2),
+            );
+            println!("{:?}", t);
+            i += 1;
+        }
+        assert_eq!(i, 2 * 2 * 5 * 3);
+    }
+
+    #[test]
+    fn test_gen_in() {
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            let a = gen.gen_in(2..5).unwrap();
+            let b = gen.gen_in(0..=3).unwrap();
+            let c = gen.gen_in(..4).unwrap();
+            let d = gen.gen_in(..=2).unwrap();
+            assert!((2..5).contains(&a));
+            assert!((0..=3).contains(&b));
+            assert!((0..4).contains(&c));
+            assert!((0..=2).contains(&d));
+            i += 1;
+        }
+        assert_eq!(i, 3 * 4 * 4 * 3);
+    }
+
+    #[test]
+    fn test_gen_in_empty() {
+        let empty: Vec<i32> = vec![];
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            assert_eq!(gen.gen_in(0..empty.len()), None);
+            i += 1;
+        }
+        assert_eq!(i, 1);
+
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            assert_eq!(gen.gen_in(..empty.len()), None);
+            i += 1;
+        }
+        assert_eq!(i, 1);
+    }
+
+    #[test]
+    fn test_pair() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let (a, b) = gen.gen_pair(&vec).unwrap();
+            assert!(a < b);
+            println!("{:?}", (a, b));
+            i += 1;
+        }
+        // C(5, 2)
+        assert_eq!(i, 10);
+    }
+
+    #[test]
+    fn test_triple() {
+        let mut gen = Gen::new();
+        let vec = vec![1, 2, 3, 4, 5];
+        let mut i = 0;
+        while !gen.done() {
+            let (a, b, c) = gen.gen_triple(&vec).unwrap();
+            assert!(a < b && b < c);
+            println!("{:?}", (a, b, c));
+            i += 1;
+        }
+        // C(5, 3)
+        assert_eq!(i, 10);
+    }
+
+    #[test]
+    fn test_pair_triple_too_short() {
+        let mut gen = Gen::new();
+        let one = vec![1];
+        let mut i = 0;
+        while !gen.done() {
+            assert_eq!(gen.gen_pair(&one), None);
+            i += 1;
+        }
+        assert_eq!(i, 1);
+
+        let mut gen = Gen::new();
+        let empty: Vec<i32> = vec![];
+        let mut i = 0;
+        while !gen.done() {
+            assert_eq!(gen.gen_triple(&empty), None);
+            i += 1;
+        }
+        assert_eq!(i, 1);
+    }
+
+    #[test]
+    fn test_gen_option() {
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            let opt = gen.gen_option(|g| g.gen(2));
+            println!("{:?}", opt);
+            i += 1;
+        }
+        // None, plus Some(0), Some(1), Some(2)
+        assert_eq!(i, 1 + 3);
+    }
+
+    #[test]
+    fn test_gen_either() {
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            let e = gen.gen_either(|g| g.gen(1), |g| g.flip());
+            println!("{:?}", e);
+            i += 1;
+        }
+        // Left(0), Left(1), Right(false), Right(true)
+        assert_eq!(i, 2 + 2);
+    }
+
+    #[test]
+    fn test_gen_elts_map_adaptor() {
+        let mut gen = Gen::new();
+        let mut i = 0;
+        while !gen.done() {
+            let doubled: Vec<usize> = gen.gen_elts(3, 4).map(|n| n * 2).collect();
+            assert!(doubled.iter().all(|n| n % 2 == 0));
+            i += 1;
+        }
+        assert_eq!(i, (5 * 5 * 5) + (5 * 5) + 5 + 1);
+    }
+
+    #[test]
+    fn test_count() {
+        let total = Gen::count(|g| {
+            g.gen_elts(3, 4).count();
+        });
+        assert_eq!(total, (5 * 5 * 5) + (5 * 5) + 5 + 1);
+    }
+
+    #[test]
+    fn test_position_and_remaining() {
+        let vec = vec![1, 2, 3, 4, 5];
+        let total = Gen::count(|g| {
+            g.gen_comb(&vec).count();
+        });
+
+        let mut gen = Gen::new();
+        let mut last = 0;
+        while !gen.done() {
+            gen.gen_comb(&vec).count();
+            assert_eq!(gen.position(), last + 1);
+            assert_eq!(gen.size_hint(total), (gen.position(), total));
+            last = gen.position();
+        }
+        assert_eq!(last, total);
+        assert_eq!(gen.remaining(total), 0);
+    }
 }